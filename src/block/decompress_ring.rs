@@ -0,0 +1,285 @@
+//! Bounded decompression into a fixed-capacity circular buffer, for consumers that
+//! can't afford to allocate a `Vec` sized to the full uncompressed length up front.
+//!
+//! Rather than writing into a linear [`Sink`](crate::block::Sink), [`decompress_into_ring`]
+//! writes into a ring buffer of caller-chosen capacity (a power of two, at least 64 KiB -
+//! the maximum LZ4 back-reference distance). Every LZ4 offset is guaranteed to be
+//! `<= 65535`, so it always resolves into bytes still retained in the ring; whenever the
+//! buffer fills, the caller's callback is invoked with the bytes produced so far (as one
+//! or two contiguous, borrowed slices, split at the ring's wrap point rather than
+//! reassembled into a fresh allocation) so it can drain them before decoding continues.
+
+use core::convert::TryInto;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::block::{DecompressError, MINMATCH};
+
+/// Minimum, and only supported granularity for, ring buffer capacity: must cover the
+/// maximum back-reference distance so every offset stays resolvable.
+const MIN_CAPACITY: usize = 64 * 1024;
+
+/// Read an LSIC-encoded (linear small integer code) extra length, as used for literal
+/// and match lengths that overflow their 4-bit token field. See
+/// [`crate::block::decompress_safe`] for the full explanation of the encoding.
+fn read_integer(input: &[u8], input_pos: &mut usize) -> Result<u32, DecompressError> {
+    let mut n: u32 = 0;
+    loop {
+        let extra: u8 = *input
+            .get(*input_pos)
+            .ok_or(DecompressError::ExpectedAnotherByte)?;
+        *input_pos += 1;
+        n += extra as u32;
+        if extra != 0xFF {
+            break;
+        }
+    }
+    Ok(n)
+}
+
+/// Read a little-endian 16-bit match offset from the input stream.
+fn read_u16(input: &[u8], input_pos: &mut usize) -> Result<u16, DecompressError> {
+    let dst = input
+        .get(*input_pos..*input_pos + 2)
+        .ok_or(DecompressError::ExpectedAnotherByte)?;
+    *input_pos += 2;
+    Ok(u16::from_le_bytes(dst.try_into().unwrap()))
+}
+
+/// Decompresses `input` into a ring buffer of `capacity` bytes (a power of two,
+/// `>= 64 KiB`). `on_full` is invoked with the produced bytes, in logical order, every
+/// time the ring fills (and once more at the end with whatever remains); a single
+/// "batch" may be delivered across two calls if it straddles the ring's wrap point.
+pub fn decompress_into_ring<F: FnMut(&[u8])>(
+    input: &[u8],
+    capacity: usize,
+    mut on_full: F,
+) -> Result<(), DecompressError> {
+    assert!(
+        capacity.is_power_of_two() && capacity >= MIN_CAPACITY,
+        "ring buffer capacity must be a power of two >= 64 KiB"
+    );
+    let mut ring = RingWriter::new(capacity, &mut on_full);
+
+    let mut input_pos = 0;
+    loop {
+        let token = *input
+            .get(input_pos)
+            .ok_or(DecompressError::ExpectedAnotherByte)?;
+        input_pos += 1;
+
+        let mut literal_length = (token >> 4) as usize;
+        if literal_length == 15 {
+            literal_length += read_integer(input, &mut input_pos)? as usize;
+        }
+        if input_pos + literal_length > input.len() {
+            return Err(DecompressError::LiteralOutOfBounds);
+        }
+        ring.push_slice(&input[input_pos..input_pos + literal_length]);
+        input_pos += literal_length;
+
+        if input_pos >= input.len() {
+            break;
+        }
+
+        let offset = read_u16(input, &mut input_pos)? as usize;
+        let mut match_length = MINMATCH + (token & 0xF) as usize;
+        if match_length == MINMATCH + 15 {
+            match_length += read_integer(input, &mut input_pos)? as usize;
+        }
+        ring.copy_match(offset, match_length)?;
+    }
+
+    ring.flush();
+    Ok(())
+}
+
+/// Tracks a circular buffer plus enough bookkeeping to hand the caller's drain callback
+/// logically-ordered, contiguous slices (splitting across the wrap point where needed),
+/// and to resolve back references modulo capacity.
+struct RingWriter<'a, F: FnMut(&[u8])> {
+    buf: Vec<u8>,
+    mask: usize,
+    /// Total bytes ever written (monotonically increasing; physical index is `& mask`).
+    total_written: usize,
+    /// Total bytes already handed to `on_full`.
+    drained: usize,
+    on_full: &'a mut F,
+}
+
+impl<'a, F: FnMut(&[u8])> RingWriter<'a, F> {
+    fn new(capacity: usize, on_full: &'a mut F) -> Self {
+        Self { buf: vec![0u8; capacity], mask: capacity - 1, total_written: 0, drained: 0, on_full }
+    }
+
+    #[inline]
+    fn push_byte(&mut self, b: u8) {
+        let idx = self.total_written & self.mask;
+        self.buf[idx] = b;
+        self.total_written += 1;
+        if self.total_written - self.drained == self.buf.len() {
+            self.drain_full_lap();
+        }
+    }
+
+    /// Appends `data` (not itself part of the ring, so no self-aliasing to worry about),
+    /// batch-copying via `copy_from_slice` in chunks that each stay within a single wrap
+    /// segment and stop where the ring fills, rather than pushing one byte at a time.
+    fn push_slice(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let dst = self.total_written & self.mask;
+            let until_wrap = self.buf.len() - dst;
+            let until_full = self.buf.len() - (self.total_written - self.drained);
+            let chunk_len = data.len().min(until_wrap).min(until_full);
+            self.buf[dst..dst + chunk_len].copy_from_slice(&data[..chunk_len]);
+            self.total_written += chunk_len;
+            if self.total_written - self.drained == self.buf.len() {
+                self.drain_full_lap();
+            }
+            data = &data[chunk_len..];
+        }
+    }
+
+    /// Resolves a back reference `offset` bytes behind the current write position,
+    /// `match_length` bytes long.
+    fn copy_match(&mut self, offset: usize, match_length: usize) -> Result<(), DecompressError> {
+        if offset == 0 || offset > self.total_written {
+            return Err(DecompressError::OffsetOutOfBounds);
+        }
+        if offset >= match_length {
+            // Source and destination ranges can't alias, so this can be batch-copied
+            // like the linear decoder's `duplicate_slice` fast path.
+            self.copy_match_non_overlapping(offset, match_length)
+        } else {
+            // The match overlaps itself (e.g. a run-length pattern): later bytes
+            // reference earlier bytes produced by this very copy, so - like
+            // `duplicate_overlapping_slice` - it must proceed one byte at a time.
+            self.copy_match_overlapping(offset, match_length)
+        }
+    }
+
+    /// Batch-copies a non-overlapping match, splitting it at the ring's wrap point (on
+    /// either the read or write side) and at drain boundaries via `Vec::copy_within`,
+    /// which is correct here even if a chunk's physical source and destination ranges
+    /// happen to coincide or overlap (it has `memmove` semantics).
+    fn copy_match_non_overlapping(&mut self, offset: usize, match_length: usize) -> Result<(), DecompressError> {
+        let mut remaining = match_length;
+        let mut src = self.total_written - offset;
+        while remaining > 0 {
+            let src_phys = src & self.mask;
+            let dst_phys = self.total_written & self.mask;
+            let until_src_wrap = self.buf.len() - src_phys;
+            let until_dst_wrap = self.buf.len() - dst_phys;
+            let until_full = self.buf.len() - (self.total_written - self.drained);
+            let chunk = remaining.min(until_src_wrap).min(until_dst_wrap).min(until_full);
+
+            self.buf.copy_within(src_phys..src_phys + chunk, dst_phys);
+
+            self.total_written += chunk;
+            src += chunk;
+            remaining -= chunk;
+            if self.total_written - self.drained == self.buf.len() {
+                self.drain_full_lap();
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies an overlapping match one byte at a time, since each output byte may
+    /// depend on one produced earlier in this same copy.
+    fn copy_match_overlapping(&mut self, offset: usize, match_length: usize) -> Result<(), DecompressError> {
+        let mut src = self.total_written - offset;
+        for _ in 0..match_length {
+            let b = self.buf[src & self.mask];
+            self.push_byte(b);
+            src += 1;
+        }
+        Ok(())
+    }
+
+    /// Hands the caller the capacity-sized region `[drained, total_written)`, as one or
+    /// two borrowed, contiguous slices (split at the wrap point) rather than allocating
+    /// a reassembled copy.
+    fn drain_full_lap(&mut self) {
+        let start = self.drained & self.mask;
+        if start == 0 {
+            (self.on_full)(&self.buf);
+        } else {
+            (self.on_full)(&self.buf[start..]);
+            (self.on_full)(&self.buf[..start]);
+        }
+        self.drained = self.total_written;
+    }
+
+    /// Hands any bytes produced since the last full lap to the callback, again as one
+    /// or two borrowed, contiguous slices.
+    fn flush(&mut self) {
+        let remaining = self.total_written - self.drained;
+        if remaining == 0 {
+            return;
+        }
+        let start = self.drained & self.mask;
+        let end = self.total_written & self.mask;
+        if end == 0 || end > start {
+            let stop = if end == 0 { self.buf.len() } else { end };
+            (self.on_full)(&self.buf[start..stop]);
+        } else {
+            (self.on_full)(&self.buf[start..]);
+            (self.on_full)(&self.buf[..end]);
+        }
+        self.drained = self.total_written;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flushes_partial_ring_at_end_of_input() {
+        // All-literal block: token 0x40 => literal_length 4, no match.
+        let mut drained = Vec::new();
+        decompress_into_ring(&[0x40, b'a', b'b', b'c', b'd'], MIN_CAPACITY, |chunk| {
+            drained.extend_from_slice(chunk);
+        })
+        .unwrap();
+        assert_eq!(drained, b"abcd");
+    }
+
+    #[test]
+    fn decodes_non_overlapping_self_referencing_match() {
+        // literal "abcd", then a match (offset 4, length 4) copying it again via the
+        // batched `copy_within` path, then a trailing literal "e".
+        let input = [0x40, b'a', b'b', b'c', b'd', 4, 0, 0x10, b'e'];
+        let mut drained = Vec::new();
+        decompress_into_ring(&input, MIN_CAPACITY, |chunk| drained.extend_from_slice(chunk)).unwrap();
+        assert_eq!(drained, b"abcdabcde");
+    }
+
+    #[test]
+    fn decodes_overlapping_match_across_a_full_lap() {
+        // A one-byte literal 'A', then an overlapping match (offset 1) long enough to
+        // both exercise the byte-by-byte RLE path and cross the ring's wrap point
+        // (triggering `on_full` mid-decode), followed by a trailing literal 'B'.
+        let match_length: usize = 69_999;
+        let extra = match_length - (MINMATCH + 15);
+        let mut input = alloc::vec![0x1Fu8, b'A', 1, 0];
+        let mut remaining = extra;
+        while remaining >= 0xFF {
+            input.push(0xFF);
+            remaining -= 0xFF;
+        }
+        input.push(remaining as u8);
+        input.push(0x10);
+        input.push(b'B');
+
+        let mut drained = Vec::new();
+        decompress_into_ring(&input, MIN_CAPACITY, |chunk| drained.extend_from_slice(chunk)).unwrap();
+
+        assert_eq!(drained.len(), 1 + match_length + 1);
+        assert_eq!(drained[0], b'A');
+        assert!(drained[1..1 + match_length].iter().all(|&b| b == b'A'));
+        assert_eq!(*drained.last().unwrap(), b'B');
+    }
+}
@@ -1,7 +1,11 @@
 //! https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md
 pub mod compress;
 pub mod decompress;
+pub mod decompress_ring;
+pub mod decompress_safe;
+pub mod decompress_stream;
 pub mod decompress_unchecked;
+pub mod varint;
 
 
 /// https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md#end-of-block-restrictions
@@ -0,0 +1,127 @@
+//! LEB128-style variable-length size prefix, as an alternative to the fixed 4-byte
+//! `u32` prefix used by `compress_prepend_size`/`decompress_size_prepended`.
+//!
+//! Each byte carries 7 payload bits in its low bits; the high bit is set on every byte
+//! except the last, signalling that another byte follows. This is the same scheme
+//! `snap` uses for its stream header. For payloads under 128 bytes this saves 3 header
+//! bytes over the fixed-width prefix, and under 16 KiB it saves 2 - useful when
+//! `lz4_flex` is compressing many small records (e.g. per-row or per-key values).
+
+use alloc::vec::Vec;
+
+use crate::block::compress::compress_into;
+use crate::block::decompress_safe::decompress;
+use crate::block::DecompressError;
+
+/// Maximum number of bytes we're willing to read (7 payload bits each) before giving up
+/// on an encoding that can no longer fit in a `usize`.
+const MAX_VARINT_BYTES: usize = (usize::BITS as usize).div_ceil(7);
+
+/// Appends `value` to `output` using the LEB128-style varint encoding.
+fn write_varint(output: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let mut byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        output.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+/// Reads a varint-encoded length from the start of `input`, returning it along with the
+/// remaining bytes.
+///
+/// Rejects encodings that would overflow `usize`, and incomplete encodings that run out
+/// of input before a byte with a clear high bit is found.
+fn read_varint(input: &[u8]) -> Result<(usize, &[u8]), DecompressError> {
+    let mut value: usize = 0;
+    for (i, &byte) in input.iter().take(MAX_VARINT_BYTES).enumerate() {
+        let payload = (byte & 0x7F) as usize;
+        let shift = i * 7;
+        // `checked_shl` only rejects a shift amount >= the bit width; it doesn't catch
+        // payload bits that get shifted *past* the top of a `usize`, which is exactly
+        // what an overlong encoding does on its last byte (e.g. 9 continuation bytes of
+        // 0 followed by a terminator byte with payload >= 2 encodes `>= 2 * 2^63`).
+        // Check those high bits explicitly before shifting.
+        if shift > 0 && payload >> (usize::BITS as usize - shift) != 0 {
+            return Err(DecompressError::VarintOverflow);
+        }
+        value |= payload << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, &input[i + 1..]));
+        }
+    }
+    if input.len() >= MAX_VARINT_BYTES {
+        return Err(DecompressError::VarintOverflow);
+    }
+    Err(DecompressError::ExpectedAnotherByte)
+}
+
+/// Compresses `input` and prepends its length as a varint, for use with
+/// [`decompress_size_prepended_varint`].
+pub fn compress_prepend_size_varint(input: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    write_varint(&mut compressed, input.len());
+    compress_into(input, &mut compressed).unwrap();
+    compressed
+}
+
+/// Decompresses `input`, whose leading bytes are a varint-encoded uncompressed length,
+/// as produced by [`compress_prepend_size_varint`].
+pub fn decompress_size_prepended_varint(input: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let (uncompressed_size, rest) = read_varint(input)?;
+    decompress(rest, uncompressed_size)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn varint_roundtrip_small() {
+        let input = b"hello world";
+        let compressed = compress_prepend_size_varint(input);
+        // "hello world" is 11 bytes, fits in a single varint byte.
+        assert_eq!(compressed[0], 11);
+        let decompressed = decompress_size_prepended_varint(&compressed).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn varint_roundtrip_multi_byte_length() {
+        let input = alloc::vec![b'a'; 1000];
+        let compressed = compress_prepend_size_varint(&input);
+        // 1000 > 127, so the length needs two bytes, the first with its continuation bit set.
+        assert_eq!(compressed[0] & 0x80, 0x80);
+        let decompressed = decompress_size_prepended_varint(&compressed).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn rejects_overlong_varint() {
+        let overlong = [0xFFu8; 11];
+        assert!(matches!(
+            decompress_size_prepended_varint(&overlong),
+            Err(DecompressError::VarintOverflow)
+        ));
+    }
+
+    #[test]
+    fn rejects_varint_that_overflows_only_on_its_final_byte() {
+        // 9 continuation bytes encoding zero, followed by a terminating byte whose
+        // payload (2) has a bit set past position 63. The shift amount itself (63)
+        // never exceeds `usize::BITS`, so a check that only guards the shift amount
+        // (rather than the resulting value) would silently truncate this to 0 instead
+        // of rejecting it.
+        let mut overlong = [0x80u8; 10];
+        overlong[9] = 0x02;
+        assert!(matches!(
+            decompress_size_prepended_varint(&overlong),
+            Err(DecompressError::VarintOverflow)
+        ));
+    }
+}
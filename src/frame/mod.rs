@@ -0,0 +1,249 @@
+//! Support for the standardized [LZ4 Frame format](https://github.com/lz4/lz4/blob/dev/doc/lz4_Frame_format.md),
+//! as opposed to the bespoke size-prepended raw blocks the rest of this crate produces.
+//! This is the format written by the `lz4` CLI and other frame-format implementations,
+//! so reading and writing it makes `lz4_flex` interoperable with them.
+
+mod xxhash32;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::block::compress::compress_into;
+use crate::block::decompress_safe::decompress_into;
+use crate::block::decompress_stream::BlockStreamDecoder;
+use crate::block::{DecompressError, Sink};
+use xxhash32::xxh32;
+
+const MAGIC_NUMBER: u32 = 0x184D2204;
+const END_MARK: u32 = 0;
+
+/// Mandatory 2-bit Version field (FLG bits 7-6), required by the spec to be `01`.
+/// See <https://github.com/lz4/lz4/blob/dev/doc/lz4_Frame_format.md#flg-byte>.
+const FLG_VERSION: u8 = 0b0100_0000;
+const FLG_VERSION_MASK: u8 = 0b1100_0000;
+const FLG_BLOCK_INDEPENDENCE: u8 = 0b0010_0000;
+const FLG_BLOCK_CHECKSUM: u8 = 0b0001_0000;
+const FLG_CONTENT_SIZE: u8 = 0b0000_1000;
+const FLG_CONTENT_CHECKSUM: u8 = 0b0000_0100;
+const FLG_DICT_ID: u8 = 0b0000_0001;
+
+/// Marks a block's length prefix as an uncompressed (stored) block rather than an
+/// LZ4-compressed one.
+const UNCOMPRESSED_BLOCK_FLAG: u32 = 0x8000_0000;
+
+/// BD value used when writing a frame: `4` => 64 KiB blocks.
+const DEFAULT_BD: u8 = 4;
+
+/// Decodes the BD byte's block-max-size field (bits 6-4, values `4..=7`) into the actual
+/// maximum number of uncompressed bytes a block may hold.
+/// See <https://github.com/lz4/lz4/blob/dev/doc/lz4_Frame_format.md#bd-byte>.
+fn block_max_size(bd: u8) -> Result<usize, DecompressError> {
+    match (bd >> 4) & 0x7 {
+        4 => Ok(64 * 1024),
+        5 => Ok(256 * 1024),
+        6 => Ok(1024 * 1024),
+        7 => Ok(4 * 1024 * 1024),
+        _ => Err(DecompressError::FrameInvalidBlockSize),
+    }
+}
+
+/// The parsed frame descriptor: everything between the magic number and the first
+/// block's length prefix.
+struct FrameDescriptor {
+    block_independence: bool,
+    block_checksums: bool,
+    content_checksum: bool,
+    block_max_size: usize,
+}
+
+fn parse_header(input: &[u8]) -> Result<(FrameDescriptor, &[u8]), DecompressError> {
+    if input.len() < 4 {
+        return Err(DecompressError::ExpectedAnotherByte);
+    }
+    let magic = u32::from_le_bytes([input[0], input[1], input[2], input[3]]);
+    if magic != MAGIC_NUMBER {
+        return Err(DecompressError::FrameMagicMismatch);
+    }
+
+    let mut pos = 4;
+    let header_start = pos;
+    let flg = *input.get(pos).ok_or(DecompressError::ExpectedAnotherByte)?;
+    pos += 1;
+    let bd = *input.get(pos).ok_or(DecompressError::ExpectedAnotherByte)?;
+    pos += 1;
+
+    if flg & FLG_VERSION_MASK != FLG_VERSION {
+        return Err(DecompressError::FrameVersionMismatch);
+    }
+
+    if flg & FLG_CONTENT_SIZE != 0 {
+        pos += 8;
+    }
+    if flg & FLG_DICT_ID != 0 {
+        pos += 4;
+    }
+    let expected = *input.get(pos).ok_or(DecompressError::ExpectedAnotherByte)? as u32;
+    pos += 1;
+    let actual = (xxh32(&input[header_start..pos - 1], 0) >> 8) as u8 as u32;
+    if actual != expected {
+        return Err(DecompressError::ChecksumMismatch { expected, actual });
+    }
+
+    let descriptor = FrameDescriptor {
+        block_independence: flg & FLG_BLOCK_INDEPENDENCE != 0,
+        block_checksums: flg & FLG_BLOCK_CHECKSUM != 0,
+        content_checksum: flg & FLG_CONTENT_CHECKSUM != 0,
+        block_max_size: block_max_size(bd)?,
+    };
+    Ok((descriptor, &input[pos..]))
+}
+
+/// Decompresses a complete LZ4 frame (magic number, descriptor, blocks, `EndMark` and
+/// optional content checksum) as produced by [`compress_frame`] or an external
+/// frame-format encoder.
+pub fn decompress_frame(input: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let (descriptor, mut rest) = parse_header(input)?;
+
+    let mut output = Vec::new();
+    let mut stream_decoder = BlockStreamDecoder::new();
+
+    loop {
+        if rest.len() < 4 {
+            return Err(DecompressError::ExpectedAnotherByte);
+        }
+        let block_size_field = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        rest = &rest[4..];
+
+        if block_size_field == END_MARK {
+            break;
+        }
+
+        let is_uncompressed = block_size_field & UNCOMPRESSED_BLOCK_FLAG != 0;
+        let block_len = (block_size_field & !UNCOMPRESSED_BLOCK_FLAG) as usize;
+        if rest.len() < block_len {
+            return Err(DecompressError::ExpectedAnotherByte);
+        }
+        let block_data = &rest[..block_len];
+        rest = &rest[block_len..];
+
+        if descriptor.block_checksums {
+            if rest.len() < 4 {
+                return Err(DecompressError::ExpectedAnotherByte);
+            }
+            rest = &rest[4..];
+        }
+
+        if is_uncompressed {
+            output.extend_from_slice(block_data);
+        } else if descriptor.block_independence {
+            let mut block_out = vec![0u8; descriptor.block_max_size];
+            let mut sink: Sink = (&mut block_out).into();
+            let n = decompress_into(block_data, &mut sink)?;
+            output.extend_from_slice(&block_out[..n]);
+        } else {
+            // Linked blocks: back references may reach into the output of earlier
+            // blocks, so route through the sliding-window decoder. The frame format
+            // doesn't carry each block's exact decompressed length, only the
+            // descriptor's upper bound, so decode until the block's input is exhausted.
+            let decoded = stream_decoder.decompress_block_bounded(block_data, descriptor.block_max_size)?;
+            output.extend_from_slice(&decoded);
+        }
+    }
+
+    if descriptor.content_checksum {
+        if rest.len() < 4 {
+            return Err(DecompressError::ExpectedAnotherByte);
+        }
+        let expected = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        let actual = xxh32(&output, 0);
+        if actual != expected {
+            return Err(DecompressError::ChecksumMismatch { expected, actual });
+        }
+    }
+
+    Ok(output)
+}
+
+/// Compresses `input` into a block-independent LZ4 frame with a content checksum,
+/// splitting it into one block per `DEFAULT_BD`'s declared 64 KiB block-max-size so that
+/// no block's decompressed size ever exceeds what the descriptor promises.
+pub fn compress_frame(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC_NUMBER.to_le_bytes());
+
+    let flg = FLG_VERSION | FLG_BLOCK_INDEPENDENCE | FLG_CONTENT_CHECKSUM;
+    let bd = DEFAULT_BD << 4;
+    let header_start = out.len();
+    out.push(flg);
+    out.push(bd);
+    let header_checksum = (xxh32(&out[header_start..], 0) >> 8) as u8;
+    out.push(header_checksum);
+
+    let block_max_size = block_max_size(bd).unwrap();
+    let mut compressed_block = Vec::new();
+    for chunk in input.chunks(block_max_size) {
+        compressed_block.clear();
+        compress_into(chunk, &mut compressed_block).unwrap();
+        out.extend_from_slice(&(compressed_block.len() as u32).to_le_bytes());
+        out.extend_from_slice(&compressed_block);
+    }
+
+    out.extend_from_slice(&END_MARK.to_le_bytes());
+    out.extend_from_slice(&xxh32(input, 0).to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip_single_block() {
+        let input = b"a frame-format roundtrip test, a frame-format roundtrip test";
+        let frame = compress_frame(input);
+        assert_eq!(&frame[0..4], &MAGIC_NUMBER.to_le_bytes());
+        let decompressed = decompress_frame(&frame).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn roundtrip_multi_block_input() {
+        // Larger than the 64 KiB default block-max-size, so this must be split across
+        // several blocks on encode and sized from the descriptor's BD byte on decode.
+        let input = vec![0u8; 200_000];
+        let frame = compress_frame(&input);
+        let decompressed = decompress_frame(&frame).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn rejects_bad_magic_number() {
+        let err = decompress_frame(&[0, 0, 0, 0]).unwrap_err();
+        assert!(matches!(err, DecompressError::FrameMagicMismatch));
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let mut frame = compress_frame(b"version check");
+        frame[4] &= !FLG_VERSION_MASK; // clear the version bits the spec requires to be `01`
+        let err = decompress_frame(&frame).unwrap_err();
+        assert!(matches!(err, DecompressError::FrameVersionMismatch));
+    }
+
+    #[test]
+    fn detects_header_checksum_mismatch() {
+        let mut frame = compress_frame(b"header checksum check");
+        frame[6] ^= 0xFF; // header checksum byte: magic(4) + flg(1) + bd(1)
+        let err = decompress_frame(&frame).unwrap_err();
+        assert!(matches!(err, DecompressError::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn detects_content_checksum_mismatch() {
+        let mut frame = compress_frame(b"checksum me");
+        let last = frame.len() - 1;
+        frame[last] ^= 0xFF;
+        let err = decompress_frame(&frame).unwrap_err();
+        assert!(matches!(err, DecompressError::ChecksumMismatch { .. }));
+    }
+}
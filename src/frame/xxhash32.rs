@@ -0,0 +1,80 @@
+//! A small, self-contained implementation of the XXH32 non-cryptographic hash, used by
+//! the [`super`] frame format for its content checksum. Vendored rather than pulled in
+//! as a dependency, the same way the block codec itself has no dependencies.
+
+const PRIME32_1: u32 = 2654435761;
+const PRIME32_2: u32 = 2246822519;
+const PRIME32_3: u32 = 3266489917;
+const PRIME32_4: u32 = 668265263;
+const PRIME32_5: u32 = 374761393;
+
+#[inline]
+fn round(acc: u32, input: u32) -> u32 {
+    let acc = acc.wrapping_add(input.wrapping_mul(PRIME32_2));
+    acc.rotate_left(13).wrapping_mul(PRIME32_1)
+}
+
+#[inline]
+fn read_u32(input: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], input[pos + 3]])
+}
+
+/// Computes the XXH32 checksum of `input` with the given `seed`.
+pub fn xxh32(input: &[u8], seed: u32) -> u32 {
+    let len = input.len();
+    let mut pos = 0;
+
+    let mut h32 = if len >= 16 {
+        let mut v1 = seed.wrapping_add(PRIME32_1).wrapping_add(PRIME32_2);
+        let mut v2 = seed.wrapping_add(PRIME32_2);
+        let mut v3 = seed;
+        let mut v4 = seed.wrapping_sub(PRIME32_1);
+
+        while pos + 16 <= len {
+            v1 = round(v1, read_u32(input, pos));
+            v2 = round(v2, read_u32(input, pos + 4));
+            v3 = round(v3, read_u32(input, pos + 8));
+            v4 = round(v4, read_u32(input, pos + 12));
+            pos += 16;
+        }
+
+        v1.rotate_left(1)
+            .wrapping_add(v2.rotate_left(7))
+            .wrapping_add(v3.rotate_left(12))
+            .wrapping_add(v4.rotate_left(18))
+    } else {
+        seed.wrapping_add(PRIME32_5)
+    };
+
+    h32 = h32.wrapping_add(len as u32);
+
+    while pos + 4 <= len {
+        h32 = h32.wrapping_add(read_u32(input, pos).wrapping_mul(PRIME32_3));
+        h32 = h32.rotate_left(17).wrapping_mul(PRIME32_4);
+        pos += 4;
+    }
+
+    while pos < len {
+        h32 = h32.wrapping_add((input[pos] as u32).wrapping_mul(PRIME32_5));
+        h32 = h32.rotate_left(11).wrapping_mul(PRIME32_1);
+        pos += 1;
+    }
+
+    h32 ^= h32 >> 15;
+    h32 = h32.wrapping_mul(PRIME32_2);
+    h32 ^= h32 >> 13;
+    h32 = h32.wrapping_mul(PRIME32_3);
+    h32 ^= h32 >> 16;
+    h32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn empty_input_matches_known_value() {
+        // Reference value for XXH32("", seed=0), taken from the XXH32 test vectors.
+        assert_eq!(xxh32(&[], 0), 0x02CC_5D05);
+    }
+}
@@ -0,0 +1,23 @@
+//! `lz4_flex` is a fast LZ4 compression and decompression library written in pure Rust.
+//!
+//! It implements the raw LZ4 block format, plus a handful of higher-level wrappers built
+//! on top of it: size-prepended blocks, linked block streams (back-references spanning
+//! block boundaries) and `std::io`-based adapters for incremental consumption.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod block;
+pub mod frame;
+#[cfg(feature = "std")]
+pub mod read;
+
+pub use crate::block::decompress_ring::decompress_into_ring;
+pub use crate::block::decompress_safe::{decompress, decompress_size_prepended};
+pub use crate::block::varint::{compress_prepend_size_varint, decompress_size_prepended_varint};
+pub use crate::frame::{compress_frame, decompress_frame};
+#[cfg(feature = "std")]
+pub use crate::read::BlockStreamReader;
@@ -0,0 +1,205 @@
+//! `std::io::Read` adapters for incremental decompression, mirroring the design of
+//! `snap`'s `read.rs`.
+
+use std::convert::TryInto;
+use std::io;
+use std::vec::Vec;
+
+use crate::block::decompress_stream::BlockStreamDecoder;
+
+/// Refuse to buffer a block larger than this, to avoid an adversarial or corrupted
+/// length prefix forcing an unbounded allocation.
+const MAX_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Wraps a reader that yields a sequence of blocks, each prefixed by its compressed and
+/// uncompressed length as little-endian `u32`s, and exposes the decompressed bytes
+/// through `Read`/`BufRead`.
+///
+/// Blocks are read and decompressed lazily, one at a time, via [`BlockStreamDecoder`],
+/// so back references may reach into previously produced blocks without requiring the
+/// caller to know the total uncompressed size up front or hold the whole stream in
+/// memory.
+pub struct BlockStreamReader<R> {
+    reader: R,
+    decoder: BlockStreamDecoder,
+    compressed_buf: Vec<u8>,
+    output_buf: Vec<u8>,
+    output_pos: usize,
+}
+
+impl<R: io::Read> BlockStreamReader<R> {
+    /// Creates a new decoder reading length-prefixed blocks from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            decoder: BlockStreamDecoder::new(),
+            compressed_buf: Vec::new(),
+            output_buf: Vec::new(),
+            output_pos: 0,
+        }
+    }
+
+    /// Returns the wrapped reader, discarding any buffered state.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads and decompresses the next block into `output_buf`. Returns `false` only if
+    /// the underlying reader is exhausted right at a block boundary (zero bytes read);
+    /// a reader that's cut off partway through the header is reported as an error
+    /// instead, the same as a truncation partway through a block's body.
+    fn fill_next_block(&mut self) -> io::Result<bool> {
+        let mut header = [0u8; 8];
+        let mut filled = 0;
+        while filled < header.len() {
+            let n = self.reader.read(&mut header[filled..])?;
+            if n == 0 {
+                if filled == 0 {
+                    return Ok(false);
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "lz4_flex: stream ended partway through a block header",
+                ));
+            }
+            filled += n;
+        }
+        let compressed_len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let uncompressed_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        if compressed_len > MAX_BLOCK_SIZE || uncompressed_len > MAX_BLOCK_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "lz4_flex: block size exceeds the maximum allowed size",
+            ));
+        }
+
+        self.compressed_buf.resize(compressed_len, 0);
+        self.reader.read_exact(&mut self.compressed_buf)?;
+
+        self.output_buf = self
+            .decoder
+            .decompress_block(&self.compressed_buf, uncompressed_len)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.output_pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: io::Read> io::Read for BlockStreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.output_pos >= self.output_buf.len() && !self.fill_next_block()? {
+            return Ok(0);
+        }
+        let available = &self.output_buf[self.output_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+impl<R: io::Read> io::BufRead for BlockStreamReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.output_pos >= self.output_buf.len() {
+            self.fill_next_block()?;
+        }
+        Ok(&self.output_buf[self.output_pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.output_pos = (self.output_pos + amt).min(self.output_buf.len());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{BufRead, Cursor, Read};
+
+    /// Builds a length-prefixed block stream out of hand-crafted, all-literal LZ4
+    /// blocks (token `len << 4`, no match), one per entry in `literals`.
+    fn build_stream(literals: &[&[u8]]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for lit in literals {
+            assert!(lit.len() < 15, "test helper only supports single-token literals");
+            let mut block = Vec::new();
+            block.push((lit.len() as u8) << 4);
+            block.extend_from_slice(lit);
+            stream.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            stream.extend_from_slice(&(lit.len() as u32).to_le_bytes());
+            stream.extend_from_slice(&block);
+        }
+        stream
+    }
+
+    #[test]
+    fn reads_multi_block_stream_through_small_buffers() {
+        let stream = build_stream(&[b"Hello, ", b"world!"]);
+        let mut reader = BlockStreamReader::new(Cursor::new(stream));
+
+        let mut out = Vec::new();
+        let mut chunk = [0u8; 3];
+        loop {
+            let n = reader.read(&mut chunk).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(out, b"Hello, world!");
+    }
+
+    #[test]
+    fn fill_buf_and_consume_cross_block_boundary() {
+        let stream = build_stream(&[b"Hello, ", b"world!"]);
+        let mut reader = BlockStreamReader::new(Cursor::new(stream));
+
+        let mut out = Vec::new();
+        loop {
+            let available = reader.fill_buf().unwrap().len();
+            if available == 0 {
+                break;
+            }
+            // Consume one byte at a time so `fill_buf` is exercised repeatedly both
+            // within a block and across the boundary into the next one.
+            out.push(reader.fill_buf().unwrap()[0]);
+            reader.consume(1);
+        }
+        assert_eq!(out, b"Hello, world!");
+    }
+
+    #[test]
+    fn errors_on_block_truncated_before_its_declared_length() {
+        let mut stream = build_stream(&[b"Hello, "]);
+        stream.truncate(stream.len() - 2); // chop the end off the block body
+        let mut reader = BlockStreamReader::new(Cursor::new(stream));
+
+        let mut buf = [0u8; 16];
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn errors_on_stream_truncated_partway_through_a_header() {
+        let mut stream = build_stream(&[b"Hello, "]);
+        stream.truncate(3); // cut off partway through the 8-byte length header
+        let mut reader = BlockStreamReader::new(Cursor::new(stream));
+
+        let mut buf = [0u8; 16];
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn clean_eof_at_a_block_boundary_is_not_an_error() {
+        let stream = build_stream(&[b"Hello, "]);
+        let mut reader = BlockStreamReader::new(Cursor::new(stream));
+
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"Hello, ");
+    }
+}